@@ -0,0 +1,483 @@
+// Copyright 2021 The Druid Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A [`Formatter`] that produces and validates RFC-style timestamps, e.g.
+//! `2024-10-22T14:00:00Z`.
+
+use std::ops::Range;
+
+use chrono::{Datelike, NaiveDate, NaiveDateTime, Timelike};
+
+use super::{Formatter, Selection, Validation, ValidationError};
+
+/// A field of a date or time that a [`DateTimeFormatter`]'s pattern can
+/// contain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Year,
+    Month,
+    Day,
+    Hour,
+    Minute,
+    Second,
+}
+
+impl Field {
+    /// The number of digits this field occupies.
+    fn width(self) -> usize {
+        match self {
+            Field::Year => 4,
+            _ => 2,
+        }
+    }
+
+    /// The smallest value this field may legally take.
+    fn min(self) -> u32 {
+        match self {
+            Field::Month | Field::Day => 1,
+            _ => 0,
+        }
+    }
+
+    /// The largest value this field may legally take, given the year and
+    /// month parsed so far (both default to `1` if not yet known, which is
+    /// permissive enough for partial input).
+    fn max(self, year: i32, month: u32) -> u32 {
+        match self {
+            Field::Year => 9999,
+            Field::Month => 12,
+            Field::Day => days_in_month(year, month),
+            Field::Hour => 23,
+            Field::Minute => 59,
+            Field::Second => 59,
+        }
+    }
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    let (next_year, next_month) = if month == 12 { (year + 1, 1) } else { (year, month + 1) };
+    match (
+        NaiveDate::from_ymd_opt(year, month, 1),
+        NaiveDate::from_ymd_opt(next_year, next_month, 1),
+    ) {
+        (Some(this), Some(next)) => next.signed_duration_since(this).num_days() as u32,
+        _ => 31,
+    }
+}
+
+/// A single token in a compiled [`DateTimeFormatter`] pattern.
+#[derive(Debug, Clone, Copy)]
+enum Token {
+    /// A date/time field, e.g. the `%Y` in `%Y-%m-%d`.
+    Field(Field),
+    /// A literal separator character, e.g. the `-` or `T` in `%Y-%m-%dT%H`.
+    Literal(char),
+    /// A `%z` timezone offset: either `Z` (UTC) or `+HH:MM`/`-HH:MM`.
+    Offset,
+}
+
+fn compile_pattern(pattern: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '%' {
+            match chars.next() {
+                Some('Y') => tokens.push(Token::Field(Field::Year)),
+                Some('m') => tokens.push(Token::Field(Field::Month)),
+                Some('d') => tokens.push(Token::Field(Field::Day)),
+                Some('H') => tokens.push(Token::Field(Field::Hour)),
+                Some('M') => tokens.push(Token::Field(Field::Minute)),
+                Some('S') => tokens.push(Token::Field(Field::Second)),
+                Some('z') => tokens.push(Token::Offset),
+                Some(other) => tokens.push(Token::Literal(other)),
+                None => {}
+            }
+        } else {
+            tokens.push(Token::Literal(c));
+        }
+    }
+    tokens
+}
+
+/// A [`Formatter`] that renders and validates timestamps according to an
+/// RFC 6350/RFC 3339-style pattern, e.g. `19961022T140000Z` or
+/// `2024-10-22T14:00:00Z`.
+///
+/// The pattern is built from `%Y` (4-digit year), `%m`/`%d` (2-digit month
+/// and day), `%H`/`%M`/`%S` (2-digit hour, minute, second), `%z` (a `Z` or
+/// `+HH:MM`/`-HH:MM` offset), and literal separator characters.
+///
+/// [`validate_partial_input`] walks the pattern field by field alongside the
+/// input: a prefix like `2024-1` is accepted (it could still become
+/// `2024-10-22`), but `2024-13` is rejected as soon as the month is fully
+/// typed and out of range. Where the next typed character is the start of a
+/// field but the pattern expects a literal separator first, the separator is
+/// inserted automatically via [`Validation::change_text`].
+///
+/// [`Formatter`]: super::Formatter
+/// [`validate_partial_input`]: super::Formatter::validate_partial_input
+pub struct DateTimeFormatter {
+    pattern: String,
+    tokens: Vec<Token>,
+}
+
+impl DateTimeFormatter {
+    /// Create a new formatter from a pattern string, as described above.
+    pub fn new(pattern: impl Into<String>) -> Self {
+        let pattern = pattern.into();
+        let tokens = compile_pattern(&pattern);
+        DateTimeFormatter { pattern, tokens }
+    }
+
+    /// The pattern this formatter was constructed with.
+    pub fn pattern(&self) -> &str {
+        &self.pattern
+    }
+}
+
+impl Default for DateTimeFormatter {
+    /// The RFC 3339-like default: `2024-10-22T14:00:00Z`.
+    fn default() -> Self {
+        DateTimeFormatter::new("%Y-%m-%dT%H:%M:%SZ")
+    }
+}
+
+/// The fields accumulated while walking a pattern against some input.
+#[derive(Default, Clone, Copy)]
+struct Parsed {
+    year: Option<i32>,
+    month: Option<u32>,
+    day: Option<u32>,
+    hour: Option<u32>,
+    minute: Option<u32>,
+    second: Option<u32>,
+}
+
+/// The result of matching one token against the input at `pos`.
+enum Step {
+    /// The token matched completely; continue at the new input position.
+    Matched(usize),
+    /// Input ran out partway through the token; this is a valid, incomplete
+    /// prefix.
+    Partial,
+    /// The token's value was out of range; `span` is the offending region.
+    Invalid { span: Range<usize> },
+    /// The input diverges from the pattern and a separator should be
+    /// auto-inserted at `at` to get it back on track. `at` isn't always the
+    /// token's starting `pos`: inside a `%z` offset, the missing `:` belongs
+    /// between the hour and minute digits, not at the start of the offset.
+    NeedsSeparator { insert: char, at: usize },
+}
+
+fn digits_at(input: &str, pos: usize, max_width: usize) -> (&str, usize) {
+    let rest = &input[pos..];
+    let end = rest
+        .char_indices()
+        .take(max_width)
+        .take_while(|(_, c)| c.is_ascii_digit())
+        .map(|(i, c)| i + c.len_utf8())
+        .last()
+        .unwrap_or(0);
+    (&rest[..end], end)
+}
+
+fn step_field(field: Field, input: &str, pos: usize, parsed: &mut Parsed) -> Step {
+    let (digits, consumed) = digits_at(input, pos, field.width());
+    if consumed < field.width() {
+        // Ran out of input, or hit a non-digit, before the field was full.
+        if pos + consumed == input.len() {
+            return Step::Partial;
+        }
+        let offender_len = input[pos + consumed..].chars().next().map_or(1, |c| c.len_utf8());
+        return Step::Invalid {
+            span: pos + consumed..pos + consumed + offender_len,
+        };
+    }
+    let value: u32 = digits.parse().unwrap();
+    let year = parsed.year.unwrap_or(1);
+    let month = parsed.month.unwrap_or(1);
+    if value < field.min() || value > field.max(year, month) {
+        return Step::Invalid {
+            span: pos..pos + consumed,
+        };
+    }
+    match field {
+        Field::Year => parsed.year = Some(value as i32),
+        Field::Month => parsed.month = Some(value),
+        Field::Day => parsed.day = Some(value),
+        Field::Hour => parsed.hour = Some(value),
+        Field::Minute => parsed.minute = Some(value),
+        Field::Second => parsed.second = Some(value),
+    }
+    Step::Matched(pos + consumed)
+}
+
+fn step_literal(expected: char, input: &str, pos: usize) -> Step {
+    match input[pos..].chars().next() {
+        None => Step::Partial,
+        Some(c) if c == expected => Step::Matched(pos + c.len_utf8()),
+        Some(c) if c.is_ascii_digit() => Step::NeedsSeparator { insert: expected, at: pos },
+        Some(c) => Step::Invalid {
+            span: pos..pos + c.len_utf8(),
+        },
+    }
+}
+
+fn step_offset(input: &str, pos: usize) -> Step {
+    let rest = &input[pos..];
+    if rest.is_empty() {
+        return Step::Partial;
+    }
+    if rest.starts_with('Z') {
+        return Step::Matched(pos + 1);
+    }
+    if let Some(sign) = rest.chars().next().filter(|&c| c == '+' || c == '-') {
+        let after_sign = pos + sign.len_utf8();
+        let (hh, hh_len) = digits_at(input, after_sign, 2);
+        if hh_len < 2 {
+            return Step::Partial;
+        }
+        if hh.parse::<u32>().unwrap_or(24) > 23 {
+            return Step::Invalid {
+                span: after_sign..after_sign + hh_len,
+            };
+        }
+        let after_hh = after_sign + hh_len;
+        match input[after_hh..].chars().next() {
+            None => Step::Partial,
+            Some(':') => {
+                let after_colon = after_hh + 1;
+                let (mm, mm_len) = digits_at(input, after_colon, 2);
+                if mm_len < 2 {
+                    return Step::Partial;
+                }
+                if mm.parse::<u32>().unwrap_or(60) > 59 {
+                    return Step::Invalid {
+                        span: after_colon..after_colon + mm_len,
+                    };
+                }
+                Step::Matched(after_colon + mm_len)
+            }
+            Some(c) if c.is_ascii_digit() => Step::NeedsSeparator { insert: ':', at: after_hh },
+            Some(c) => Step::Invalid {
+                span: after_hh..after_hh + c.len_utf8(),
+            },
+        }
+    } else {
+        let len = rest.chars().next().map_or(1, |c| c.len_utf8());
+        Step::Invalid {
+            span: pos..pos + len,
+        }
+    }
+}
+
+impl Formatter<NaiveDateTime> for DateTimeFormatter {
+    fn format(&self, value: &NaiveDateTime) -> String {
+        let mut out = String::new();
+        for token in &self.tokens {
+            match token {
+                Token::Field(Field::Year) => out.push_str(&format!("{:04}", value.year())),
+                Token::Field(Field::Month) => out.push_str(&format!("{:02}", value.month())),
+                Token::Field(Field::Day) => out.push_str(&format!("{:02}", value.day())),
+                Token::Field(Field::Hour) => out.push_str(&format!("{:02}", value.hour())),
+                Token::Field(Field::Minute) => out.push_str(&format!("{:02}", value.minute())),
+                Token::Field(Field::Second) => out.push_str(&format!("{:02}", value.second())),
+                Token::Literal(c) => out.push(*c),
+                // `NaiveDateTime` carries no timezone, so the offset is
+                // always written as `Z`. `value` checks the shape of a
+                // `+HH:MM`/`-HH:MM` offset but does not apply it to the
+                // returned time.
+                Token::Offset => out.push('Z'),
+            }
+        }
+        out
+    }
+
+    fn validate_partial_input(&self, input: &str, sel: &Selection) -> Validation {
+        let mut parsed = Parsed::default();
+        let mut pos = 0;
+        for token in &self.tokens {
+            let step = match token {
+                Token::Field(field) => step_field(*field, input, pos, &mut parsed),
+                Token::Literal(c) => step_literal(*c, input, pos),
+                Token::Offset => step_offset(input, pos),
+            };
+            match step {
+                Step::Matched(new_pos) => pos = new_pos,
+                Step::Partial => return Validation::success(),
+                Step::Invalid { span } => {
+                    return Validation::failure(ValidationError::out_of_range().spanning(span))
+                }
+                Step::NeedsSeparator { insert, at } => {
+                    let mut text = input.to_string();
+                    text.insert(at, insert);
+                    // The separator is inserted at `at`; only shift the
+                    // caret if it sat at or after that point. If the caret
+                    // is earlier in the string (e.g. the user is editing an
+                    // earlier field), it shouldn't move.
+                    let caret = if sel.active >= at {
+                        sel.active + 1
+                    } else {
+                        sel.active
+                    };
+                    return Validation::success()
+                        .change_text(text)
+                        .change_selection(Selection::caret(caret));
+                }
+            }
+        }
+        if pos < input.len() {
+            // Trailing characters the pattern doesn't account for.
+            return Validation::failure(ValidationError::invalid_character(pos).spanning(pos..input.len()));
+        }
+        Validation::success()
+    }
+
+    fn value(&self, input: &str) -> Result<NaiveDateTime, ValidationError> {
+        let mut parsed = Parsed::default();
+        let mut pos = 0;
+        for token in &self.tokens {
+            let step = match token {
+                Token::Field(field) => step_field(*field, input, pos, &mut parsed),
+                Token::Literal(c) => step_literal(*c, input, pos),
+                Token::Offset => step_offset(input, pos),
+            };
+            match step {
+                Step::Matched(new_pos) => pos = new_pos,
+                Step::Partial => {
+                    return Err(ValidationError::incomplete().spanning(pos..input.len()))
+                }
+                Step::Invalid { span } => {
+                    return Err(ValidationError::out_of_range().spanning(span))
+                }
+                Step::NeedsSeparator { at, .. } => {
+                    return Err(ValidationError::invalid_character(at).spanning(at..at + 1))
+                }
+            }
+        }
+        if pos < input.len() {
+            return Err(ValidationError::invalid_character(pos).spanning(pos..input.len()));
+        }
+        let date = NaiveDate::from_ymd_opt(
+            parsed.year.unwrap_or(1),
+            parsed.month.unwrap_or(1),
+            parsed.day.unwrap_or(1),
+        )
+        .ok_or_else(|| ValidationError::with_message(format!("'{}' is not a valid date", input)))?;
+        date.and_hms_opt(
+            parsed.hour.unwrap_or(0),
+            parsed.minute.unwrap_or(0),
+            parsed.second.unwrap_or(0),
+        )
+        .ok_or_else(|| ValidationError::with_message(format!("'{}' is not a valid time", input)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn formatter() -> DateTimeFormatter {
+        DateTimeFormatter::default()
+    }
+
+    #[test]
+    fn formats_value() {
+        let f = formatter();
+        let value = NaiveDate::from_ymd_opt(2024, 10, 22)
+            .unwrap()
+            .and_hms_opt(14, 0, 0)
+            .unwrap();
+        assert_eq!(f.format(&value), "2024-10-22T14:00:00Z");
+    }
+
+    #[test]
+    fn value_parses_complete_input() {
+        let f = formatter();
+        let value = f.value("2024-10-22T14:00:00Z").unwrap();
+        assert_eq!(value.year(), 2024);
+        assert_eq!(value.month(), 10);
+        assert_eq!(value.day(), 22);
+        assert_eq!(value.hour(), 14);
+    }
+
+    #[test]
+    fn validate_partial_input_accepts_prefix() {
+        let f = formatter();
+        let sel = Selection::caret(6);
+        assert!(!f.validate_partial_input("2024-1", &sel).is_err());
+    }
+
+    #[test]
+    fn validate_partial_input_rejects_out_of_range_field() {
+        let f = formatter();
+        let sel = Selection::caret(7);
+        assert!(f.validate_partial_input("2024-13", &sel).is_err());
+    }
+
+    #[test]
+    fn invalid_char_span_does_not_split_a_multi_byte_character() {
+        let f = formatter();
+        let input = "2024日10-22T14:00:00Z";
+        let sel = Selection::caret(0);
+        let validation = f.validate_partial_input(input, &sel);
+        assert!(validation.is_err());
+        let span = validation.error().unwrap().span().unwrap();
+        assert_eq!(&input[span], "日");
+    }
+
+    #[test]
+    fn invalid_char_span_covers_only_the_offending_character() {
+        let f = formatter();
+        let input = "20a4-10-22T14:00:00Z";
+        let sel = Selection::caret(0);
+        let validation = f.validate_partial_input(input, &sel);
+        assert!(validation.is_err());
+        let span = validation.error().unwrap().span().unwrap();
+        assert_eq!(span, 2..3);
+        assert_eq!(&input[span], "a");
+    }
+
+    #[test]
+    fn validate_partial_input_auto_inserts_separator() {
+        let f = formatter();
+        let sel = Selection::caret(7);
+        let validation = f.validate_partial_input("2024-1022", &sel);
+        assert_eq!(validation.text_change.as_deref(), Some("2024-10-22"));
+        assert_eq!(validation.selection_change.unwrap().active, 8);
+    }
+
+    #[test]
+    fn validate_partial_input_does_not_shift_caret_before_inserted_separator() {
+        // The caret sits in the hour field, just before the hour/minute
+        // separator that's about to be auto-inserted; it should stay put.
+        let f = formatter();
+        let sel = Selection::caret(12);
+        let validation = f.validate_partial_input("2024-10-22T1400:00Z", &sel);
+        assert_eq!(validation.text_change.as_deref(), Some("2024-10-22T14:00:00Z"));
+        assert_eq!(validation.selection_change.unwrap().active, 12);
+    }
+
+    #[test]
+    fn validate_partial_input_auto_inserts_offset_separator() {
+        // The offset token starts at byte 19, well before where the missing
+        // `:` actually belongs (between the hour and minute of `+1400`); the
+        // colon must land there, not get spliced into the start of the token.
+        let f = DateTimeFormatter::new("%Y-%m-%dT%H:%M:%S%z");
+        let sel = Selection::caret(24);
+        let validation = f.validate_partial_input("2024-10-22T14:00:00+1400", &sel);
+        assert_eq!(validation.text_change.as_deref(), Some("2024-10-22T14:00:00+14:00"));
+        assert_eq!(validation.selection_change.unwrap().active, 25);
+    }
+}
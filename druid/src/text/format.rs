@@ -15,6 +15,7 @@
 //! Creating, interpreting, and validating textual representations of values.
 
 use super::Selection;
+use std::ops::Range;
 use std::str::FromStr;
 
 /// A trait for types that create, interpret, and validate textual representations
@@ -58,7 +59,15 @@ pub trait Formatter<T> {
     /// If you do *not* explicitly set replacement text, and validation is not
     /// successful, the edit will be ignored.
     ///
+    /// If validation fails with a [`ValidationError`] that carries a
+    /// [`span`], you don't need to set `selection_change` yourself to convey
+    /// the offending region: a consuming widget may use the span to select
+    /// it automatically, instead of you wiring up a `selection_change` by
+    /// hand.
+    ///
     /// [`Validation`]: Validation
+    /// [`ValidationError`]: ValidationError
+    /// [`span`]: ValidationError::span
     fn validate_partial_input(&self, input: &str, sel: &Selection) -> Validation;
 
     /// The value represented by the input, or an error if the input is invalid.
@@ -78,6 +87,12 @@ pub struct ParseFormatter;
 /// The result of a [`Formatter`] attempting to validate some partial input.
 pub struct Validation {
     result: Result<(), ValidationError>,
+    /// Non-fatal diagnostics.
+    ///
+    /// Unlike `result`, these do not prevent the edit from being accepted;
+    /// they are intended for advisory messages (such as "this looks like a
+    /// typo") that a widget may want to display without blocking the user.
+    warnings: Vec<ValidationError>,
     /// A manual selection override.
     ///
     /// This will be set as the new selection (regardless of whether or not
@@ -91,14 +106,47 @@ pub struct Validation {
 }
 
 /// An error that occurs when attempting to parse text input.
-//FIXME: remove this 'message' stuff and force people to use a real error type
-//like FromStr does
+///
+/// This is `#[non_exhaustive]` so that new kinds can be added without a
+/// breaking change, and deliberately does not expose any dependency error
+/// types in its public variants; an arbitrary error can still be attached
+/// and retrieved through [`source`](std::error::Error::source).
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum ValidationError {
-    /// An error describing the failure.
-    Err(Box<dyn std::error::Error>),
-    /// A String describing the failure.
-    Message(String),
+    /// The value was outside some acceptable range.
+    OutOfRange {
+        span: Option<Range<usize>>,
+        slice: Option<Range<usize>>,
+        source: Option<Box<dyn std::error::Error>>,
+    },
+    /// The input was longer than `max` characters.
+    TooLong {
+        max: usize,
+        span: Option<Range<usize>>,
+        slice: Option<Range<usize>>,
+        source: Option<Box<dyn std::error::Error>>,
+    },
+    /// The character at `index` was not a valid character for this value.
+    InvalidCharacter {
+        index: usize,
+        span: Option<Range<usize>>,
+        slice: Option<Range<usize>>,
+        source: Option<Box<dyn std::error::Error>>,
+    },
+    /// The input is a valid, incomplete prefix of a value, but is not a
+    /// value itself.
+    Incomplete {
+        span: Option<Range<usize>>,
+        slice: Option<Range<usize>>,
+    },
+    /// Any other failure.
+    Other {
+        message: Option<String>,
+        span: Option<Range<usize>>,
+        slice: Option<Range<usize>>,
+        source: Option<Box<dyn std::error::Error>>,
+    },
 }
 
 impl ValidationError {
@@ -106,12 +154,234 @@ impl ValidationError {
     ///
     /// [`Error`]: std::error::Error
     pub fn from_err(err: impl std::error::Error + 'static) -> Self {
-        ValidationError::Err(Box::new(err))
+        ValidationError::Other {
+            message: None,
+            span: None,
+            slice: None,
+            source: Some(Box::new(err)),
+        }
+    }
+
+    /// Construct a `ValidationError` with a `String` describing the failure.
+    pub fn with_message(msg: impl Into<String>) -> Self {
+        ValidationError::Other {
+            message: Some(msg.into()),
+            span: None,
+            slice: None,
+            source: None,
+        }
+    }
+
+    /// Construct a `ValidationError` with a `String` and a `span`: the byte
+    /// range into the validated input that the error applies to.
+    ///
+    /// A widget can use this to, for instance, automatically set a
+    /// [`Selection`] over the offending characters.
+    ///
+    /// [`Selection`]: super::Selection
+    pub fn with_span(msg: impl Into<String>, span: Range<usize>) -> Self {
+        ValidationError::Other {
+            message: Some(msg.into()),
+            span: Some(span),
+            slice: None,
+            source: None,
+        }
+    }
+
+    /// Construct an [`OutOfRange`] error.
+    ///
+    /// [`OutOfRange`]: ValidationError::OutOfRange
+    pub fn out_of_range() -> Self {
+        ValidationError::OutOfRange {
+            span: None,
+            slice: None,
+            source: None,
+        }
+    }
+
+    /// Construct a [`TooLong`] error, for input longer than `max` characters.
+    ///
+    /// [`TooLong`]: ValidationError::TooLong
+    pub fn too_long(max: usize) -> Self {
+        ValidationError::TooLong {
+            max,
+            span: None,
+            slice: None,
+            source: None,
+        }
+    }
+
+    /// Construct an [`InvalidCharacter`] error, for the character at `index`.
+    ///
+    /// [`InvalidCharacter`]: ValidationError::InvalidCharacter
+    pub fn invalid_character(index: usize) -> Self {
+        ValidationError::InvalidCharacter {
+            index,
+            span: None,
+            slice: None,
+            source: None,
+        }
+    }
+
+    /// Construct an [`Incomplete`] error, for input that is a valid prefix
+    /// of a value but not yet a value itself.
+    ///
+    /// [`Incomplete`]: ValidationError::Incomplete
+    pub fn incomplete() -> Self {
+        ValidationError::Incomplete {
+            span: None,
+            slice: None,
+        }
+    }
+
+    /// Set the `span`: the byte range into the validated input that this
+    /// error applies to.
+    pub fn spanning(mut self, span: Range<usize>) -> Self {
+        *self.span_mut() = Some(span);
+        self
+    }
+
+    /// Set the recovery `slice`: the range spanning from the last valid
+    /// fragment of the input to the next point at which validation might
+    /// recover.
+    pub fn with_slice(mut self, slice: Range<usize>) -> Self {
+        *self.slice_mut() = Some(slice);
+        self
+    }
+
+    /// Attach a `source` error, retrievable via
+    /// [`source`](std::error::Error::source).
+    ///
+    /// This is a no-op on [`Incomplete`](ValidationError::Incomplete), which
+    /// has no source slot: incomplete input isn't itself a failure, so there
+    /// is nothing to attribute it to.
+    pub fn with_source(mut self, source: impl std::error::Error + 'static) -> Self {
+        if let Some(slot) = self.source_mut() {
+            *slot = Some(Box::new(source));
+        }
+        self
+    }
+
+    fn span_mut(&mut self) -> &mut Option<Range<usize>> {
+        match self {
+            ValidationError::OutOfRange { span, .. }
+            | ValidationError::TooLong { span, .. }
+            | ValidationError::InvalidCharacter { span, .. }
+            | ValidationError::Incomplete { span, .. }
+            | ValidationError::Other { span, .. } => span,
+        }
+    }
+
+    fn slice_mut(&mut self) -> &mut Option<Range<usize>> {
+        match self {
+            ValidationError::OutOfRange { slice, .. }
+            | ValidationError::TooLong { slice, .. }
+            | ValidationError::InvalidCharacter { slice, .. }
+            | ValidationError::Incomplete { slice, .. }
+            | ValidationError::Other { slice, .. } => slice,
+        }
+    }
+
+    fn source_mut(&mut self) -> Option<&mut Option<Box<dyn std::error::Error>>> {
+        match self {
+            ValidationError::OutOfRange { source, .. }
+            | ValidationError::TooLong { source, .. }
+            | ValidationError::InvalidCharacter { source, .. }
+            | ValidationError::Other { source, .. } => Some(source),
+            ValidationError::Incomplete { .. } => None,
+        }
+    }
+
+    fn source_ref(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ValidationError::OutOfRange { source, .. }
+            | ValidationError::TooLong { source, .. }
+            | ValidationError::InvalidCharacter { source, .. }
+            | ValidationError::Other { source, .. } => source.as_deref(),
+            ValidationError::Incomplete { .. } => None,
+        }
+    }
+
+    /// The byte range into the validated input that this error applies to,
+    /// if one was set.
+    pub fn span(&self) -> Option<Range<usize>> {
+        match self {
+            ValidationError::OutOfRange { span, .. }
+            | ValidationError::TooLong { span, .. }
+            | ValidationError::InvalidCharacter { span, .. }
+            | ValidationError::Incomplete { span, .. }
+            | ValidationError::Other { span, .. } => span.clone(),
+        }
+    }
+
+    /// The recovery slice for this error, if one was set.
+    ///
+    /// This spans from the last valid fragment of the input to the next
+    /// point at which validation might recover.
+    pub fn slice(&self) -> Option<Range<usize>> {
+        match self {
+            ValidationError::OutOfRange { slice, .. }
+            | ValidationError::TooLong { slice, .. }
+            | ValidationError::InvalidCharacter { slice, .. }
+            | ValidationError::Incomplete { slice, .. }
+            | ValidationError::Other { slice, .. } => slice.clone(),
+        }
+    }
+
+    /// Returns `true` if this is an [`OutOfRange`] error.
+    ///
+    /// [`OutOfRange`]: ValidationError::OutOfRange
+    pub fn is_out_of_range(&self) -> bool {
+        matches!(self, ValidationError::OutOfRange { .. })
+    }
+
+    /// Returns `true` if this is a [`TooLong`] error.
+    ///
+    /// [`TooLong`]: ValidationError::TooLong
+    pub fn is_too_long(&self) -> bool {
+        matches!(self, ValidationError::TooLong { .. })
     }
 
-    /// Construct a `ValidationError` with a `String`.
-    pub fn with_message(msg: String) -> Self {
-        ValidationError::Message(msg)
+    /// Returns `true` if this is an [`InvalidCharacter`] error.
+    ///
+    /// [`InvalidCharacter`]: ValidationError::InvalidCharacter
+    pub fn is_invalid_character(&self) -> bool {
+        matches!(self, ValidationError::InvalidCharacter { .. })
+    }
+
+    /// Returns `true` if this is an [`Incomplete`] error.
+    ///
+    /// [`Incomplete`]: ValidationError::Incomplete
+    pub fn is_incomplete(&self) -> bool {
+        matches!(self, ValidationError::Incomplete { .. })
+    }
+
+    /// Returns `true` if this is an [`Other`] error.
+    ///
+    /// [`Other`]: ValidationError::Other
+    pub fn is_other(&self) -> bool {
+        matches!(self, ValidationError::Other { .. })
+    }
+
+    /// If this is a [`TooLong`] error, the maximum length that was exceeded.
+    ///
+    /// [`TooLong`]: ValidationError::TooLong
+    pub fn max_len(&self) -> Option<usize> {
+        match self {
+            ValidationError::TooLong { max, .. } => Some(*max),
+            _ => None,
+        }
+    }
+
+    /// If this is an [`InvalidCharacter`] error, the index of the offending
+    /// character.
+    ///
+    /// [`InvalidCharacter`]: ValidationError::InvalidCharacter
+    pub fn invalid_index(&self) -> Option<usize> {
+        match self {
+            ValidationError::InvalidCharacter { index, .. } => Some(*index),
+            _ => None,
+        }
     }
 }
 
@@ -120,6 +390,7 @@ impl Validation {
     pub fn success() -> Self {
         Validation {
             result: Ok(()),
+            warnings: Vec::new(),
             selection_change: None,
             text_change: None,
         }
@@ -128,7 +399,7 @@ impl Validation {
     /// Create a `Validation` with an error indicating the failure reason.
     pub fn failure_with_err(err: impl std::error::Error + 'static) -> Self {
         Validation {
-            result: Err(ValidationError::Err(Box::new(err))),
+            result: Err(ValidationError::from_err(err)),
             ..Validation::success()
         }
     }
@@ -136,7 +407,25 @@ impl Validation {
     /// Create a `Validation` with a String indicating the failure reason.
     pub fn failure_with_message(message: impl Into<String>) -> Self {
         Validation {
-            result: Err(ValidationError::Message(message.into())),
+            result: Err(ValidationError::with_message(message.into())),
+            ..Validation::success()
+        }
+    }
+
+    /// Create a `Validation` with an already-constructed [`ValidationError`].
+    ///
+    /// Prefer this over [`failure_with_err`] or [`failure_with_message`] when
+    /// you've built a [`ValidationError`] with a [`span`] or [`slice`], so
+    /// that information isn't discarded.
+    ///
+    /// [`ValidationError`]: ValidationError
+    /// [`failure_with_err`]: Validation::failure_with_err
+    /// [`failure_with_message`]: Validation::failure_with_message
+    /// [`span`]: ValidationError::span
+    /// [`slice`]: ValidationError::slice
+    pub fn failure(error: ValidationError) -> Self {
+        Validation {
+            result: Err(error),
             ..Validation::success()
         }
     }
@@ -153,6 +442,17 @@ impl Validation {
         self
     }
 
+    /// Add a non-fatal warning to this `Validation`.
+    ///
+    /// Warnings do not affect whether this `Validation` is considered a
+    /// success or a failure; they are advisory diagnostics (such as "this
+    /// looks like a typo") that a widget may choose to display to the user
+    /// without blocking the edit.
+    pub fn with_warning(mut self, warning: ValidationError) -> Self {
+        self.warnings.push(warning);
+        self
+    }
+
     /// Returns `true` if this `Validation` indicates success.
     pub fn is_err(&self) -> bool {
         self.result.is_err()
@@ -164,6 +464,17 @@ impl Validation {
     pub fn error(&self) -> Option<&ValidationError> {
         self.result.as_ref().err()
     }
+
+    /// Returns the non-fatal warnings accumulated during validation.
+    ///
+    /// This is empty unless the [`Formatter`] explicitly attached warnings
+    /// with [`with_warning`], regardless of whether validation succeeded.
+    ///
+    /// [`Formatter`]: Formatter
+    /// [`with_warning`]: Validation::with_warning
+    pub fn warnings(&self) -> &[ValidationError] {
+        &self.warnings
+    }
 }
 
 impl<T> Formatter<T> for ParseFormatter
@@ -190,17 +501,125 @@ where
 impl std::fmt::Display for ValidationError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            ValidationError::Err(err) => err.fmt(f),
-            ValidationError::Message(s) => s.fmt(f),
+            ValidationError::OutOfRange { .. } => write!(f, "value is out of range"),
+            ValidationError::TooLong { max, .. } => {
+                write!(f, "value is longer than the maximum of {} characters", max)
+            }
+            ValidationError::InvalidCharacter { index, .. } => {
+                write!(f, "invalid character at index {}", index)
+            }
+            ValidationError::Incomplete { .. } => write!(f, "input is incomplete"),
+            ValidationError::Other {
+                message: Some(message),
+                ..
+            } => message.fmt(f),
+            ValidationError::Other {
+                message: None,
+                source: Some(source),
+                ..
+            } => source.fmt(f),
+            ValidationError::Other { .. } => write!(f, "invalid input"),
         }
     }
 }
 impl std::error::Error for ValidationError {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
-        if let ValidationError::Err(e) = self {
-            Some(e.as_ref())
-        } else {
-            None
-        }
+        self.source_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn success_has_no_warnings_by_default() {
+        let validation = Validation::success();
+        assert!(validation.warnings().is_empty());
+    }
+
+    #[test]
+    fn with_warning_does_not_affect_success_or_failure() {
+        let success = Validation::success().with_warning(ValidationError::with_message("looks like a typo"));
+        assert!(!success.is_err());
+        assert_eq!(success.warnings().len(), 1);
+
+        let failure =
+            Validation::failure_with_message("bad input").with_warning(ValidationError::with_message("also bad"));
+        assert!(failure.is_err());
+        assert_eq!(failure.warnings().len(), 1);
+    }
+
+    #[test]
+    fn with_warning_accumulates_multiple_warnings_in_order() {
+        let validation = Validation::success()
+            .with_warning(ValidationError::with_message("first"))
+            .with_warning(ValidationError::with_message("second"));
+        let messages: Vec<String> = validation.warnings().iter().map(|w| w.to_string()).collect();
+        assert_eq!(messages, vec!["first", "second"]);
+    }
+
+    #[test]
+    fn span_is_none_by_default() {
+        let err = ValidationError::with_message("bad input");
+        assert_eq!(err.span(), None);
+        assert_eq!(err.slice(), None);
+    }
+
+    #[test]
+    fn with_span_sets_the_span() {
+        let err = ValidationError::with_span("bad input", 2..5);
+        assert_eq!(err.span(), Some(2..5));
+    }
+
+    #[test]
+    fn spanning_overrides_the_span_on_any_variant() {
+        let err = ValidationError::out_of_range().spanning(1..4);
+        assert_eq!(err.span(), Some(1..4));
+    }
+
+    #[test]
+    fn with_slice_sets_the_recovery_slice_independently_of_span() {
+        let err = ValidationError::invalid_character(3).spanning(3..4).with_slice(3..8);
+        assert_eq!(err.span(), Some(3..4));
+        assert_eq!(err.slice(), Some(3..8));
+    }
+
+    #[test]
+    fn predicates_distinguish_each_kind() {
+        let out_of_range = ValidationError::out_of_range();
+        assert!(out_of_range.is_out_of_range());
+        assert!(!out_of_range.is_too_long());
+        assert!(!out_of_range.is_invalid_character());
+        assert!(!out_of_range.is_incomplete());
+        assert!(!out_of_range.is_other());
+
+        let too_long = ValidationError::too_long(10);
+        assert!(too_long.is_too_long());
+        assert!(!too_long.is_out_of_range());
+
+        let invalid_character = ValidationError::invalid_character(3);
+        assert!(invalid_character.is_invalid_character());
+        assert!(!invalid_character.is_too_long());
+
+        let incomplete = ValidationError::incomplete();
+        assert!(incomplete.is_incomplete());
+        assert!(!incomplete.is_other());
+
+        let other = ValidationError::with_message("something else went wrong");
+        assert!(other.is_other());
+        assert!(!other.is_incomplete());
+    }
+
+    #[test]
+    fn max_len_is_only_set_on_too_long() {
+        assert_eq!(ValidationError::too_long(10).max_len(), Some(10));
+        assert_eq!(ValidationError::out_of_range().max_len(), None);
+    }
+
+    #[test]
+    fn invalid_index_is_only_set_on_invalid_character() {
+        assert_eq!(ValidationError::invalid_character(3).invalid_index(), Some(3));
+        assert_eq!(ValidationError::out_of_range().invalid_index(), None);
     }
 }
@@ -0,0 +1,827 @@
+// Copyright 2021 The Druid Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A locale-aware [`Formatter`] for numbers.
+//!
+//! [`Formatter`]: super::Formatter
+
+use super::{Formatter, Selection, Validation, ValidationError};
+
+/// How negative values should be displayed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NegativeStyle {
+    /// A leading minus sign, e.g. `-42`.
+    Minus,
+    /// Wrapped in parentheses, e.g. `(42)`, as is common for accounting.
+    Parentheses,
+}
+
+/// How many fractional digits a [`NumberFormatter`] should display.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FractionDigits {
+    /// Always show exactly this many fractional digits, padding with zeros.
+    Fixed(usize),
+    /// Show up to this many fractional digits, trimming trailing zeros.
+    UpTo(usize),
+}
+
+/// A locale-aware [`Formatter`] for numbers.
+///
+/// The docs for [`Formatter::format_for_editing`] describe wanting to show a
+/// dollar sign while idle but hide it while editing; this type is the
+/// concrete implementation of that idea. It supports a configurable
+/// thousands-grouping separator, a fixed or bounded number of fractional
+/// digits, an optional prefix or suffix (for a currency symbol or unit), and
+/// a choice of negative representation.
+///
+/// [`format`] produces the grouped, decorated string. [`format_for_editing`]
+/// strips the grouping separator and prefix/suffix so the raw digits are
+/// what gets edited. [`validate_partial_input`] accepts in-progress states
+/// (a lone `-`, a trailing decimal point, a partial digit group) and
+/// re-inserts the grouping separator as the user types, adjusting the
+/// selection to compensate. [`value`] parses the cleaned string.
+///
+/// `NumberFormatter` implements [`Formatter`] for `f64` as well as every
+/// built-in integer type (`i8`\-`i128`, `u8`\-`u128`, `isize`, `usize`). The
+/// integer impls parse and format through the integer type itself rather
+/// than round-tripping through `f64`, so an `i64`/`u64` bound to, say, an
+/// amount in cents doesn't silently lose precision above 2^53; they ignore
+/// `fraction_digits` and `decimal_separator`, since integers have no
+/// fractional part.
+///
+/// [`Formatter::format_for_editing`]: super::Formatter::format_for_editing
+/// [`format`]: super::Formatter::format
+/// [`format_for_editing`]: super::Formatter::format_for_editing
+/// [`validate_partial_input`]: super::Formatter::validate_partial_input
+/// [`value`]: super::Formatter::value
+pub struct NumberFormatter {
+    grouping_separator: char,
+    decimal_separator: char,
+    fraction_digits: FractionDigits,
+    prefix: String,
+    suffix: String,
+    negative_style: NegativeStyle,
+}
+
+impl Default for NumberFormatter {
+    fn default() -> Self {
+        NumberFormatter {
+            grouping_separator: ',',
+            decimal_separator: '.',
+            fraction_digits: FractionDigits::UpTo(0),
+            prefix: String::new(),
+            suffix: String::new(),
+            negative_style: NegativeStyle::Minus,
+        }
+    }
+}
+
+impl NumberFormatter {
+    /// Create a new `NumberFormatter` with default settings: a `,` grouping
+    /// separator, a `.` decimal separator, no fractional digits, no
+    /// prefix/suffix, and a leading minus sign for negative values.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the character used to separate groups of thousands, e.g. `,` in
+    /// `1,234`.
+    pub fn grouping_separator(mut self, separator: char) -> Self {
+        self.grouping_separator = separator;
+        self
+    }
+
+    /// Set the character used to separate the integer and fractional parts.
+    pub fn decimal_separator(mut self, separator: char) -> Self {
+        self.decimal_separator = separator;
+        self
+    }
+
+    /// Set how many fractional digits are displayed.
+    pub fn fraction_digits(mut self, digits: FractionDigits) -> Self {
+        self.fraction_digits = digits;
+        self
+    }
+
+    /// Set a string to display before the number, e.g. `"$"`.
+    pub fn prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// Set a string to display after the number, e.g. `" kg"`.
+    pub fn suffix(mut self, suffix: impl Into<String>) -> Self {
+        self.suffix = suffix.into();
+        self
+    }
+
+    /// Set how negative values are displayed.
+    pub fn negative_style(mut self, style: NegativeStyle) -> Self {
+        self.negative_style = style;
+        self
+    }
+
+    /// Insert `self.grouping_separator` every three digits of `digits`,
+    /// counting from the right.
+    fn group(&self, digits: &str) -> String {
+        let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+        for (i, c) in digits.chars().enumerate() {
+            let from_right = digits.len() - i;
+            // `is_multiple_of` reads better here but isn't available on the
+            // crate's MSRV yet; allow the newer clippy lint suggesting it.
+            #[allow(clippy::manual_is_multiple_of)]
+            let at_group_boundary = from_right % 3 == 0;
+            if i > 0 && at_group_boundary {
+                grouped.push(self.grouping_separator);
+            }
+            grouped.push(c);
+        }
+        grouped
+    }
+
+    /// Render `value` as `prefix` + grouped digits + `suffix`, applying the
+    /// configured negative style and fraction-digit rules.
+    fn format_f64(&self, value: f64) -> String {
+        let negative = value.is_sign_negative() && value != 0.0;
+        let magnitude = value.abs();
+
+        let text = match self.fraction_digits {
+            FractionDigits::Fixed(n) => format!("{:.*}", n, magnitude),
+            FractionDigits::UpTo(n) => trim_trailing_zeros(&format!("{:.*}", n, magnitude)),
+        };
+
+        let (int_part, frac_part) = match text.split_once('.') {
+            Some((int_part, frac_part)) => (int_part, Some(frac_part)),
+            None => (text.as_str(), None),
+        };
+
+        let mut body = self.group(int_part);
+        if let Some(frac_part) = frac_part {
+            body.push(self.decimal_separator);
+            body.push_str(frac_part);
+        }
+
+        let mut out = String::new();
+        out.push_str(&self.prefix);
+        if negative && self.negative_style == NegativeStyle::Parentheses {
+            out.push('(');
+            out.push_str(&body);
+            out.push(')');
+        } else {
+            if negative {
+                out.push('-');
+            }
+            out.push_str(&body);
+        }
+        out.push_str(&self.suffix);
+        out
+    }
+
+    /// Remove the prefix, suffix, and grouping separators from `input`,
+    /// leaving only a sign, digits, and an optional decimal separator.
+    ///
+    /// Returns `None` if `input` is negative, but written in a
+    /// representation other than the one configured by `self.negative_style`
+    /// (e.g. a leading `-` when the style is [`NegativeStyle::Parentheses`]):
+    /// that's not a genuine numeric value, just a character that happens to
+    /// parse, and must be rejected rather than silently renormalized.
+    fn strip_decoration(&self, input: &str) -> Option<String> {
+        let mut s = input.trim();
+        if let Some(rest) = s.strip_prefix(self.prefix.as_str()) {
+            if !self.prefix.is_empty() {
+                s = rest;
+            }
+        }
+        if let Some(rest) = s.strip_suffix(self.suffix.as_str()) {
+            if !self.suffix.is_empty() {
+                s = rest;
+            }
+        }
+        let s = s.trim();
+
+        let (negative, s) = if let Some(inner) = s.strip_prefix('(').and_then(|s| s.strip_suffix(')')) {
+            if self.negative_style != NegativeStyle::Parentheses {
+                return None;
+            }
+            (true, inner)
+        } else if let Some(inner) = s.strip_prefix('-') {
+            if self.negative_style != NegativeStyle::Minus {
+                return None;
+            }
+            (true, inner)
+        } else {
+            (false, s)
+        };
+
+        let cleaned: String = s.chars().filter(|&c| c != self.grouping_separator).collect();
+        let cleaned = cleaned.replace(self.decimal_separator, ".");
+
+        Some(if negative {
+            format!("-{}", cleaned)
+        } else {
+            cleaned
+        })
+    }
+
+    /// If `input` ends with a trailing decimal/grouping separator, or (with
+    /// [`NegativeStyle::Parentheses`]) opens with `(` and hasn't been closed
+    /// yet, return `input` with that decoration removed: what comes before
+    /// the trailing separator, or what follows the opening `(`. `None` means
+    /// `input` isn't mid-typing one of these decorations at all.
+    ///
+    /// This only strips the *outermost* decoration, not the full
+    /// `strip_decoration` treatment — callers still need to run the result
+    /// through `strip_decoration` to check it's a genuine numeric prefix
+    /// rather than garbage that merely ends in a separator.
+    fn partial_decoration_prefix<'a>(&self, input: &'a str, allow_decimal: bool) -> Option<&'a str> {
+        if allow_decimal && input.ends_with(self.decimal_separator) {
+            Some(&input[..input.len() - self.decimal_separator.len_utf8()])
+        } else if input.ends_with(self.grouping_separator) {
+            Some(&input[..input.len() - self.grouping_separator.len_utf8()])
+        } else if self.negative_style == NegativeStyle::Parentheses && is_open_paren_prefix(input) {
+            Some(&input['('.len_utf8()..])
+        } else {
+            None
+        }
+    }
+}
+
+impl Formatter<f64> for NumberFormatter {
+    fn format(&self, value: &f64) -> String {
+        self.format_f64(*value)
+    }
+
+    fn format_for_editing(&self, value: &f64) -> String {
+        let negative = value.is_sign_negative() && *value != 0.0;
+        let text = match self.fraction_digits {
+            FractionDigits::Fixed(n) => format!("{:.*}", n, value.abs()),
+            FractionDigits::UpTo(n) => trim_trailing_zeros(&format!("{:.*}", n, value.abs())),
+        };
+        let text = text.replace('.', &self.decimal_separator.to_string());
+        match (negative, self.negative_style) {
+            (true, NegativeStyle::Parentheses) => format!("({})", text),
+            (true, NegativeStyle::Minus) => format!("-{}", text),
+            (false, _) => text,
+        }
+    }
+
+    fn validate_partial_input(&self, input: &str, sel: &Selection) -> Validation {
+        if input.is_empty() || input == "-" {
+            return Validation::success();
+        }
+        // A trailing decimal/grouping separator or an unclosed opening
+        // parenthesis is itself a valid in-progress state, but only if
+        // what comes before it is too; check the remainder the same way a
+        // complete input is checked below rather than waving it through on
+        // the decoration shape alone.
+        if let Some(partial) = self.partial_decoration_prefix(input, true) {
+            let partial_ok = partial.is_empty()
+                || partial == "-"
+                || self
+                    .strip_decoration(partial)
+                    .is_some_and(|s| s.parse::<f64>().is_ok());
+            if partial_ok {
+                return Validation::success();
+            }
+        }
+
+        let cleaned = match self.strip_decoration(input) {
+            Some(cleaned) => cleaned,
+            None => return Validation::failure(self.invalid_char_error(input, true, true)),
+        };
+        match cleaned.parse::<f64>() {
+            Ok(_) => {
+                let regrouped = self.format_for_editing_partial(&cleaned);
+                if regrouped == input {
+                    Validation::success()
+                } else {
+                    // Separators may have shifted, so re-locate the caret by
+                    // digit count rather than byte offset: count the digits
+                    // before the caret in the old text, then walk that many
+                    // digits into the regrouped text.
+                    let digits_before_caret = digit_count(&input[..sel.active]);
+                    let caret = position_after_digits(&regrouped, digits_before_caret);
+                    Validation::success()
+                        .change_text(regrouped)
+                        .change_selection(Selection::caret(caret))
+                }
+            }
+            Err(_) => Validation::failure(self.invalid_char_error(input, true, true)),
+        }
+    }
+
+    fn value(&self, input: &str) -> Result<f64, ValidationError> {
+        self.strip_decoration(input)
+            .and_then(|s| s.parse().ok())
+            .ok_or_else(|| self.invalid_char_error(input, true, true))
+    }
+}
+
+impl NumberFormatter {
+    /// Re-insert the grouping separator into an already-cleaned numeric
+    /// string, as used while regrouping partial input during editing.
+    fn format_for_editing_partial(&self, cleaned: &str) -> String {
+        let (negative, cleaned) = match cleaned.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, cleaned),
+        };
+        let (int_part, frac_part) = match cleaned.split_once('.') {
+            Some((int_part, frac_part)) => (int_part, Some(frac_part)),
+            None => (cleaned, None),
+        };
+        let parens = negative && self.negative_style == NegativeStyle::Parentheses;
+        let mut out = String::new();
+        if parens {
+            out.push('(');
+        } else if negative {
+            out.push('-');
+        }
+        out.push_str(&self.group(int_part));
+        if let Some(frac_part) = frac_part {
+            out.push(self.decimal_separator);
+            out.push_str(frac_part);
+        }
+        if parens {
+            out.push(')');
+        }
+        out
+    }
+
+    /// Render `negative` and the unsigned decimal digits of an integer value
+    /// as `prefix` + grouped digits + `suffix`, applying the configured
+    /// negative style. Shared by every integer [`Formatter`] impl below.
+    fn format_int(&self, negative: bool, digits: &str) -> String {
+        let body = self.group(digits);
+        let mut out = String::new();
+        out.push_str(&self.prefix);
+        if negative && self.negative_style == NegativeStyle::Parentheses {
+            out.push('(');
+            out.push_str(&body);
+            out.push(')');
+        } else {
+            if negative {
+                out.push('-');
+            }
+            out.push_str(&body);
+        }
+        out.push_str(&self.suffix);
+        out
+    }
+
+    /// The raw digits of an integer value, with no grouping, prefix, or
+    /// suffix, as used while editing.
+    fn format_for_editing_int(&self, negative: bool, digits: &str) -> String {
+        match (negative, self.negative_style) {
+            (true, NegativeStyle::Parentheses) => format!("({})", digits),
+            (true, NegativeStyle::Minus) => format!("-{}", digits),
+            (false, _) => digits.to_string(),
+        }
+    }
+
+    /// Shared `validate_partial_input` for the integer [`Formatter`] impls.
+    ///
+    /// This is the integer analogue of the `f64` impl above: it accepts the
+    /// same in-progress states (minus the trailing decimal point, which has
+    /// no meaning for an integer) and regroups as the user types. The actual
+    /// bounds of `T` are enforced by [`value`](Formatter::value); this only
+    /// needs to recognize "looks like an integer so far", so it checks the
+    /// shape of the digits directly rather than parsing through a fixed-width
+    /// integer type, which would reject valid `u128`/`u64` input wider than
+    /// that type's range (e.g. a `u128` value past `i128::MAX`).
+    ///
+    /// `allow_negative` mirrors [`invalid_char_error`](Self::invalid_char_error):
+    /// it's `false` for the unsigned impls, which can't represent a negative
+    /// value at all, so a bare `-`/open-paren or a completed negative
+    /// representation is rejected here rather than waved through to fail
+    /// only once [`value`](Formatter::value) is called.
+    fn validate_partial_integer_input(&self, input: &str, sel: &Selection, allow_negative: bool) -> Validation {
+        if input.is_empty() {
+            return Validation::success();
+        }
+        if input == "-" {
+            return if allow_negative {
+                Validation::success()
+            } else {
+                Validation::failure(self.invalid_char_error(input, false, false))
+            };
+        }
+        // See the comment in the `f64` impl above: the remainder before a
+        // trailing separator or an unclosed opening parenthesis has to look
+        // like an integer itself, not just be decorated correctly. A
+        // negative-shaped partial is further gated on `allow_negative`,
+        // since an unsigned type can't represent one at all.
+        if let Some(partial) = self.partial_decoration_prefix(input, false) {
+            let partial_is_negative = partial.starts_with('-') || is_open_paren_prefix(input);
+            if allow_negative || !partial_is_negative {
+                let partial_ok = partial.is_empty()
+                    || partial == "-"
+                    || self
+                        .strip_decoration(partial)
+                        .is_some_and(|s| looks_like_integer(&s));
+                if partial_ok {
+                    return Validation::success();
+                }
+            }
+        }
+
+        let cleaned = match self.strip_decoration(input) {
+            Some(cleaned) => cleaned,
+            None => return Validation::failure(self.invalid_char_error(input, false, allow_negative)),
+        };
+        if !allow_negative && cleaned.starts_with('-') {
+            return Validation::failure(self.invalid_char_error(input, false, false));
+        }
+        if looks_like_integer(&cleaned) {
+            let regrouped = self.format_for_editing_partial(&cleaned);
+            if regrouped == input {
+                Validation::success()
+            } else {
+                let digits_before_caret = digit_count(&input[..sel.active]);
+                let caret = position_after_digits(&regrouped, digits_before_caret);
+                Validation::success()
+                    .change_text(regrouped)
+                    .change_selection(Selection::caret(caret))
+            }
+        } else {
+            Validation::failure(self.invalid_char_error(input, false, allow_negative))
+        }
+    }
+
+    /// Locate the first character of `input` that isn't a valid part of a
+    /// (possibly decorated, possibly signed) number, and report it as a
+    /// spanned [`ValidationError::InvalidCharacter`] so a `TextBox` can
+    /// select the offending region, instead of surfacing an opaque parse
+    /// error.
+    ///
+    /// `allow_decimal` controls whether `self.decimal_separator` counts as
+    /// valid (it doesn't for the integer impls). `allow_negative` controls
+    /// whether a leading sign or parenthesization is valid at all (it isn't
+    /// for unsigned integer types); when it's disallowed, the whole signed
+    /// region is reported as [`ValidationError::OutOfRange`] instead, since
+    /// the problem isn't a stray character but the sign itself.
+    ///
+    /// Falls back to [`ValidationError::Incomplete`] if every character
+    /// mirrors `strip_decoration`'s own rules but parsing still failed (e.g.
+    /// the cleaned input is empty).
+    fn invalid_char_error(&self, input: &str, allow_decimal: bool, allow_negative: bool) -> ValidationError {
+        let lead_ws = input.len() - input.trim_start().len();
+        let mut offset = lead_ws;
+        let mut s = input.trim();
+
+        if !self.prefix.is_empty() {
+            if let Some(rest) = s.strip_prefix(self.prefix.as_str()) {
+                offset += s.len() - rest.len();
+                s = rest;
+            }
+        }
+        if !self.suffix.is_empty() {
+            if let Some(rest) = s.strip_suffix(self.suffix.as_str()) {
+                s = rest;
+            }
+        }
+        let lead_ws = s.len() - s.trim_start().len();
+        offset += lead_ws;
+        s = s.trim();
+
+        let is_parens_signed = self.negative_style == NegativeStyle::Parentheses
+            && s.strip_prefix('(').and_then(|s| s.strip_suffix(')')).is_some();
+        let is_minus_signed = self.negative_style == NegativeStyle::Minus && s.starts_with('-');
+        let is_signed = is_parens_signed || is_minus_signed;
+        if is_signed && !allow_negative {
+            return ValidationError::out_of_range().spanning(offset..offset + s.len());
+        }
+        if is_parens_signed {
+            s = s.strip_prefix('(').and_then(|s| s.strip_suffix(')')).unwrap();
+            offset += 1;
+        } else if is_minus_signed {
+            s = s.strip_prefix('-').unwrap();
+            offset += 1;
+        }
+
+        let mut seen_decimal = false;
+        for (i, c) in s.char_indices() {
+            let is_decimal = allow_decimal && c == self.decimal_separator && !seen_decimal;
+            if c == self.decimal_separator {
+                seen_decimal = true;
+            }
+            if !(c.is_ascii_digit() || c == self.grouping_separator || is_decimal) {
+                let idx = offset + i;
+                return ValidationError::invalid_character(idx).spanning(idx..idx + c.len_utf8());
+            }
+        }
+
+        ValidationError::incomplete().spanning(offset..input.len())
+    }
+}
+
+/// Implements `Formatter<$t>` for `NumberFormatter` for a signed integer
+/// type, parsing and formatting through `$t` itself (via `unsigned_abs`) so
+/// precision above 2^53 survives the round trip.
+macro_rules! impl_signed_number_formatter {
+    ($($t:ty),* $(,)?) => {
+        $(
+            impl Formatter<$t> for NumberFormatter {
+                fn format(&self, value: &$t) -> String {
+                    self.format_int(*value < 0, &value.unsigned_abs().to_string())
+                }
+
+                fn format_for_editing(&self, value: &$t) -> String {
+                    self.format_for_editing_int(*value < 0, &value.unsigned_abs().to_string())
+                }
+
+                fn validate_partial_input(&self, input: &str, sel: &Selection) -> Validation {
+                    self.validate_partial_integer_input(input, sel, true)
+                }
+
+                fn value(&self, input: &str) -> Result<$t, ValidationError> {
+                    self.strip_decoration(input)
+                        .and_then(|s| s.parse().ok())
+                        .ok_or_else(|| self.invalid_char_error(input, false, true))
+                }
+            }
+        )*
+    };
+}
+
+/// Implements `Formatter<$t>` for `NumberFormatter` for an unsigned integer
+/// type. A leading sign or parenthesization is reported as
+/// [`ValidationError::OutOfRange`] rather than an invalid character, since
+/// `$t` has no representation for negative values at all.
+macro_rules! impl_unsigned_number_formatter {
+    ($($t:ty),* $(,)?) => {
+        $(
+            impl Formatter<$t> for NumberFormatter {
+                fn format(&self, value: &$t) -> String {
+                    self.format_int(false, &value.to_string())
+                }
+
+                fn format_for_editing(&self, value: &$t) -> String {
+                    self.format_for_editing_int(false, &value.to_string())
+                }
+
+                fn validate_partial_input(&self, input: &str, sel: &Selection) -> Validation {
+                    self.validate_partial_integer_input(input, sel, false)
+                }
+
+                fn value(&self, input: &str) -> Result<$t, ValidationError> {
+                    self.strip_decoration(input)
+                        .and_then(|s| s.parse().ok())
+                        .ok_or_else(|| self.invalid_char_error(input, false, false))
+                }
+            }
+        )*
+    };
+}
+
+impl_signed_number_formatter!(i8, i16, i32, i64, i128, isize);
+impl_unsigned_number_formatter!(u8, u16, u32, u64, u128, usize);
+
+/// Trim trailing zeros (and a trailing decimal point) from a formatted
+/// number, e.g. `"1.500"` -> `"1.5"`, `"1.000"` -> `"1"`.
+fn trim_trailing_zeros(s: &str) -> String {
+    if !s.contains('.') {
+        return s.to_string();
+    }
+    let trimmed = s.trim_end_matches('0');
+    let trimmed = trimmed.trim_end_matches('.');
+    trimmed.to_string()
+}
+
+/// Returns `true` for an in-progress `NegativeStyle::Parentheses` value that
+/// has been opened (`(`) but not yet closed, e.g. `"("` or `"(123"`.
+fn is_open_paren_prefix(input: &str) -> bool {
+    input.starts_with('(') && !input.contains(')')
+}
+
+/// Returns `true` if `s` is a (possibly negative) run of ASCII digits, with
+/// no bound on how many — unlike parsing through a fixed-width integer type,
+/// this doesn't reject a value just because it's wider than that type.
+fn looks_like_integer(s: &str) -> bool {
+    let digits = s.strip_prefix('-').unwrap_or(s);
+    !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit())
+}
+
+/// Count the digits in `s`, ignoring signs, separators, and any other
+/// decoration.
+fn digit_count(s: &str) -> usize {
+    s.chars().filter(|c| c.is_ascii_digit()).count()
+}
+
+/// The byte offset in `s` immediately after the `n`th digit, or `s.len()`
+/// if `s` has fewer than `n` digits.
+fn position_after_digits(s: &str, n: usize) -> usize {
+    if n == 0 {
+        return 0;
+    }
+    let mut seen = 0;
+    for (i, c) in s.char_indices() {
+        if c.is_ascii_digit() {
+            seen += 1;
+            if seen == n {
+                return i + c.len_utf8();
+            }
+        }
+    }
+    s.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn formatter() -> NumberFormatter {
+        NumberFormatter::new()
+    }
+
+    #[test]
+    fn formats_with_grouping() {
+        let f = formatter().fraction_digits(FractionDigits::UpTo(2));
+        assert_eq!(f.format(&1234567.5), "1,234,567.5");
+        assert_eq!(f.format(&42.0), "42");
+    }
+
+    #[test]
+    fn formats_negative_with_minus() {
+        let f = formatter();
+        assert_eq!(f.format(&-42.0), "-42");
+    }
+
+    #[test]
+    fn formats_negative_with_parentheses() {
+        let f = formatter().negative_style(NegativeStyle::Parentheses);
+        assert_eq!(f.format(&-1234.0), "(1,234)");
+    }
+
+    #[test]
+    fn value_parses_grouped_and_decorated_input() {
+        let f = formatter().prefix("$");
+        assert_eq!(Formatter::<f64>::value(&f, "$1,234").unwrap(), 1234.0);
+    }
+
+    #[test]
+    fn value_parses_parenthesized_negative() {
+        let f = formatter().negative_style(NegativeStyle::Parentheses);
+        assert_eq!(Formatter::<f64>::value(&f, "(1,234)").unwrap(), -1234.0);
+    }
+
+    #[test]
+    fn validate_partial_input_accepts_in_progress_states() {
+        let f = formatter();
+        let sel = Selection::caret(1);
+        assert!(!Formatter::<f64>::validate_partial_input(&f, "-", &sel).is_err());
+        assert!(!Formatter::<f64>::validate_partial_input(&f, "1,", &sel).is_err());
+        assert!(!Formatter::<f64>::validate_partial_input(&f, "1.", &sel).is_err());
+    }
+
+    #[test]
+    fn validate_partial_input_accepts_open_paren_only_with_parentheses_style() {
+        let minus = formatter();
+        let sel = Selection::caret(1);
+        assert!(Formatter::<f64>::validate_partial_input(&minus, "(", &sel).is_err());
+
+        let parens = formatter().negative_style(NegativeStyle::Parentheses);
+        assert!(!Formatter::<f64>::validate_partial_input(&parens, "(", &sel).is_err());
+        assert!(!Formatter::<f64>::validate_partial_input(&parens, "(123", &sel).is_err());
+    }
+
+    #[test]
+    fn rejects_the_other_styles_negative_representation() {
+        let sel = Selection::caret(0);
+
+        let minus = formatter();
+        assert!(Formatter::<f64>::validate_partial_input(&minus, "(5)", &sel).is_err());
+        assert!(Formatter::<f64>::value(&minus, "(5)").is_err());
+
+        let parens = formatter().negative_style(NegativeStyle::Parentheses);
+        assert!(Formatter::<f64>::validate_partial_input(&parens, "-5", &sel).is_err());
+        assert!(Formatter::<f64>::value(&parens, "-5").is_err());
+    }
+
+    #[test]
+    fn validate_partial_input_regroups_and_preserves_caret_by_digit_count() {
+        let f = formatter();
+        // Caret sits after the 4th digit; after regrouping to "1,234" it
+        // should still sit after the 4th digit, not at the old byte offset.
+        let sel = Selection::caret(4);
+        let validation = Formatter::<f64>::validate_partial_input(&f, "1234", &sel);
+        assert_eq!(validation.text_change.as_deref(), Some("1,234"));
+        assert_eq!(validation.selection_change.unwrap().active, 5);
+    }
+
+    #[test]
+    fn validate_partial_input_rejects_garbage() {
+        let f = formatter();
+        let sel = Selection::caret(0);
+        assert!(Formatter::<f64>::validate_partial_input(&f, "abc", &sel).is_err());
+    }
+
+    #[test]
+    fn validate_partial_input_rejects_garbage_before_a_trailing_separator() {
+        let f = formatter();
+        let sel = Selection::caret(0);
+        assert!(Formatter::<f64>::validate_partial_input(&f, "abc.", &sel).is_err());
+        assert!(Formatter::<f64>::validate_partial_input(&f, "xx,", &sel).is_err());
+
+        let parens = formatter().negative_style(NegativeStyle::Parentheses);
+        assert!(Formatter::<f64>::validate_partial_input(&parens, "(abc", &sel).is_err());
+    }
+
+    #[test]
+    fn validate_partial_integer_input_rejects_garbage_before_a_trailing_separator() {
+        let f = formatter();
+        let sel = Selection::caret(0);
+        assert!(Formatter::<u32>::validate_partial_input(&f, "xx,", &sel).is_err());
+
+        let parens = formatter().negative_style(NegativeStyle::Parentheses);
+        assert!(Formatter::<i32>::validate_partial_input(&parens, "(abc", &sel).is_err());
+    }
+
+    #[test]
+    fn rejects_garbage_with_a_span_over_the_offending_character() {
+        let f = formatter();
+        let sel = Selection::caret(0);
+        let validation = Formatter::<f64>::validate_partial_input(&f, "12x34", &sel);
+        let err = validation.error().unwrap();
+        assert!(err.is_invalid_character());
+        assert_eq!(err.invalid_index(), Some(2));
+        assert_eq!(err.span(), Some(2..3));
+    }
+
+    #[test]
+    fn value_reports_a_span_instead_of_an_opaque_parse_error() {
+        let f = formatter();
+        let err = Formatter::<f64>::value(&f, "12x34").unwrap_err();
+        assert!(err.is_invalid_character());
+        assert_eq!(err.span(), Some(2..3));
+    }
+
+    #[test]
+    fn formats_signed_integer_with_grouping() {
+        let f = formatter();
+        let positive: i64 = 1_234_567;
+        let negative: i64 = -1_234;
+        assert_eq!(f.format(&positive), "1,234,567");
+        assert_eq!(f.format(&negative), "-1,234");
+    }
+
+    #[test]
+    fn formats_unsigned_integer_with_grouping() {
+        let f = formatter();
+        let value: u64 = 1_234_567;
+        assert_eq!(f.format(&value), "1,234,567");
+    }
+
+    #[test]
+    fn integer_format_survives_values_f64_cannot_represent_exactly() {
+        // 2^53 + 1: the smallest positive integer that can't be represented
+        // exactly as an f64, which is exactly what round-tripping through
+        // `value: f64` would silently truncate.
+        let f = formatter();
+        let value: i64 = 9_007_199_254_740_993;
+        let formatted = f.format(&value);
+        let parsed: i64 = f.value(&formatted).unwrap();
+        assert_eq!(parsed, value);
+    }
+
+    #[test]
+    fn integer_value_parses_decorated_input() {
+        let f = formatter().prefix("$");
+        let unsigned: u32 = f.value("$1,234").unwrap();
+        assert_eq!(unsigned, 1234);
+
+        let parens = formatter().negative_style(NegativeStyle::Parentheses);
+        let signed: i32 = parens.value("(1,234)").unwrap();
+        assert_eq!(signed, -1234);
+    }
+
+    #[test]
+    fn unsigned_integer_rejects_negative_input() {
+        let f = formatter();
+        let err = f.value("-5").map(|v: u32| v).unwrap_err();
+        assert!(err.is_out_of_range());
+    }
+
+    #[test]
+    fn unsigned_integer_validate_partial_input_rejects_negative_shapes() {
+        let f = formatter();
+        let sel = Selection::caret(1);
+        assert!(Formatter::<u32>::validate_partial_input(&f, "-", &sel).is_err());
+        assert!(Formatter::<u32>::validate_partial_input(&f, "-5", &sel).is_err());
+
+        let parens = formatter().negative_style(NegativeStyle::Parentheses);
+        assert!(Formatter::<u32>::validate_partial_input(&parens, "(", &sel).is_err());
+        assert!(Formatter::<u32>::validate_partial_input(&parens, "(5", &sel).is_err());
+        assert!(Formatter::<u32>::validate_partial_input(&parens, "(5)", &sel).is_err());
+    }
+}
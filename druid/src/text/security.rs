@@ -0,0 +1,217 @@
+// Copyright 2021 The Druid Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A [`Formatter`] combinator that flags text likely to be spoofed via
+//! Unicode bidirectional overrides or mixed-script confusables.
+
+use std::ops::Range;
+
+use unicode_script::{Script, UnicodeScript};
+
+use super::{Formatter, Selection, Validation, ValidationError};
+
+/// A [`Formatter`] that wraps another formatter and adds warnings for
+/// Unicode-based spoofing tricks.
+///
+/// Text fields that accept identifiers, filenames, or URLs are vulnerable to
+/// homoglyph and bidirectional-override spoofing: a string can be made to
+/// *display* differently from how it is actually interpreted. This formatter
+/// delegates [`format`], [`format_for_editing`], and [`value`] to the inner
+/// `Formatter`, but augments [`validate_partial_input`] with two checks:
+///
+/// - **Bidi control characters**: the explicit-embedding/override codepoints
+///   U+202A–U+202E and the isolate codepoints U+2066–U+2069 can be used to
+///   visually reorder text and disguise its real content.
+/// - **Mixed scripts**: if the non-`Common`/`Inherited` characters in the
+///   input span more than one Unicode [`Script`] (for instance Latin mixed
+///   with Cyrillic), the input may contain confusable characters.
+///
+/// Both checks add a warning via [`Validation::with_warning`] rather than
+/// rejecting the input outright, so that editing can continue; a `TextBox`
+/// can use the resulting warnings to show the user advisory diagnostics.
+///
+/// [`Formatter`]: Formatter
+/// [`format`]: Formatter::format
+/// [`format_for_editing`]: Formatter::format_for_editing
+/// [`value`]: Formatter::value
+/// [`validate_partial_input`]: Formatter::validate_partial_input
+pub struct SecurityCheckedFormatter<F> {
+    inner: F,
+    check_bidi_chars: bool,
+    check_mixed_script: bool,
+}
+
+impl<F> SecurityCheckedFormatter<F> {
+    /// Wrap `inner`, with both checks enabled.
+    pub fn new(inner: F) -> Self {
+        SecurityCheckedFormatter {
+            inner,
+            check_bidi_chars: true,
+            check_mixed_script: true,
+        }
+    }
+
+    /// Toggle the bidirectional control character check.
+    pub fn check_bidi_chars(mut self, flag: bool) -> Self {
+        self.check_bidi_chars = flag;
+        self
+    }
+
+    /// Toggle the mixed-script check.
+    pub fn check_mixed_script(mut self, flag: bool) -> Self {
+        self.check_mixed_script = flag;
+        self
+    }
+}
+
+impl<T, F: Formatter<T>> Formatter<T> for SecurityCheckedFormatter<F> {
+    fn format(&self, value: &T) -> String {
+        self.inner.format(value)
+    }
+
+    fn format_for_editing(&self, value: &T) -> String {
+        self.inner.format_for_editing(value)
+    }
+
+    fn validate_partial_input(&self, input: &str, sel: &Selection) -> Validation {
+        let mut validation = self.inner.validate_partial_input(input, sel);
+
+        if self.check_bidi_chars {
+            if let Some(span) = find_bidi_control_char(input) {
+                validation = validation.with_warning(ValidationError::with_span(
+                    "input contains bidirectional control characters",
+                    span,
+                ));
+            }
+        }
+
+        if self.check_mixed_script {
+            if let Some(span) = find_mixed_script(input) {
+                validation = validation.with_warning(ValidationError::with_span(
+                    "input mixes multiple scripts, which may be a confusable",
+                    span,
+                ));
+            }
+        }
+
+        validation
+    }
+
+    fn value(&self, input: &str) -> Result<T, ValidationError> {
+        self.inner.value(input)
+    }
+}
+
+/// Returns `true` for the explicit-embedding/override and isolate Unicode
+/// bidirectional control codepoints.
+fn is_bidi_control_char(c: char) -> bool {
+    matches!(c, '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}')
+}
+
+/// Finds the first bidirectional control character in `input`, if any, and
+/// returns its byte span.
+fn find_bidi_control_char(input: &str) -> Option<Range<usize>> {
+    input
+        .char_indices()
+        .find(|(_, c)| is_bidi_control_char(*c))
+        .map(|(i, c)| i..i + c.len_utf8())
+}
+
+/// Walks `input`'s non-`Common`/`Inherited` characters and, if they span more
+/// than one [`Script`], returns the byte span from the start of the input to
+/// the character that introduced the second script.
+fn find_mixed_script(input: &str) -> Option<Range<usize>> {
+    let mut seen: Option<Script> = None;
+    for (i, c) in input.char_indices() {
+        let script = c.script();
+        if script == Script::Common || script == Script::Inherited {
+            continue;
+        }
+        match seen {
+            None => seen = Some(script),
+            Some(first) if first == script => {}
+            Some(_) => return Some(0..i + c.len_utf8()),
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A bare-bones `Formatter<String>` that accepts anything, for exercising
+    /// the security checks in isolation.
+    struct PassthroughFormatter;
+
+    impl Formatter<String> for PassthroughFormatter {
+        fn format(&self, value: &String) -> String {
+            value.clone()
+        }
+
+        fn format_for_editing(&self, value: &String) -> String {
+            value.clone()
+        }
+
+        fn validate_partial_input(&self, _input: &str, _sel: &Selection) -> Validation {
+            Validation::success()
+        }
+
+        fn value(&self, input: &str) -> Result<String, ValidationError> {
+            Ok(input.to_string())
+        }
+    }
+
+    fn formatter() -> SecurityCheckedFormatter<PassthroughFormatter> {
+        SecurityCheckedFormatter::new(PassthroughFormatter)
+    }
+
+    #[test]
+    fn clean_input_has_no_warnings() {
+        let f = formatter();
+        let sel = Selection::caret(0);
+        let validation = Formatter::<String>::validate_partial_input(&f, "hello", &sel);
+        assert!(validation.warnings().is_empty());
+    }
+
+    #[test]
+    fn flags_bidi_control_characters() {
+        let f = formatter();
+        let sel = Selection::caret(0);
+        let input = "abc\u{202E}def";
+        let validation = Formatter::<String>::validate_partial_input(&f, input, &sel);
+        assert_eq!(validation.warnings().len(), 1);
+    }
+
+    #[test]
+    fn flags_mixed_scripts() {
+        let f = formatter();
+        let sel = Selection::caret(0);
+        // Latin "a" followed by Cyrillic "а" (U+0430).
+        let input = "a\u{0430}";
+        let validation = Formatter::<String>::validate_partial_input(&f, input, &sel);
+        assert_eq!(validation.warnings().len(), 1);
+    }
+
+    #[test]
+    fn checks_can_be_disabled() {
+        let f = SecurityCheckedFormatter::new(PassthroughFormatter)
+            .check_bidi_chars(false)
+            .check_mixed_script(false);
+        let sel = Selection::caret(0);
+        let input = "a\u{0430}\u{202E}";
+        let validation = Formatter::<String>::validate_partial_input(&f, input, &sel);
+        assert!(validation.warnings().is_empty());
+    }
+}